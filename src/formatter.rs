@@ -32,33 +32,184 @@
 
 // the standard formatter types
 use std::fmt::{self, Write};
+use std::io;
 
-/// defines the default indentation level
+/// defines the default indentation width
 const DEFAULT_INDENT: usize = 4;
 
+/// Selects where the opening brace of a block is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BraceStyle {
+    /// K&R style: the opening brace stays on the current line.
+    SameLine,
+
+    /// Allman style: the opening brace goes on its own line at the current indentation.
+    NextLine,
+}
+
+/// Selects the line terminator used for generated output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewlineStyle {
+    /// Unix line endings (`\n`).
+    Unix,
+
+    /// Windows line endings (`\r\n`).
+    Windows,
+
+    /// The host platform's native line endings.
+    Native,
+}
+
+impl NewlineStyle {
+    /// Resolves the style to a concrete terminator, mapping [`Native`] to the host default.
+    ///
+    /// [`Native`]: NewlineStyle::Native
+    fn terminator(self) -> &'static str {
+        match self {
+            NewlineStyle::Unix => "\n",
+            NewlineStyle::Windows => "\r\n",
+            NewlineStyle::Native => {
+                if cfg!(windows) {
+                    "\r\n"
+                } else {
+                    "\n"
+                }
+            }
+        }
+    }
+}
+
+/// Configuration controlling how the [`Formatter`] lays out its output.
+#[derive(Debug, Clone, Copy)]
+pub struct FormatterConfig {
+    /// the number of columns a single indentation level occupies
+    pub indent_width: usize,
+
+    /// whether to indent with hard tabs instead of spaces
+    pub use_tabs: bool,
+
+    /// where the opening brace of a block is placed
+    pub brace_style: BraceStyle,
+
+    /// the line terminator used for generated output
+    pub newline_style: NewlineStyle,
+
+    /// the maximum column width before lists are wrapped onto multiple lines
+    pub max_width: usize,
+}
+
+impl FormatterConfig {
+    /// Returns the default configuration (four spaces per indentation level).
+    pub fn new() -> Self {
+        Self {
+            indent_width: DEFAULT_INDENT,
+            use_tabs: false,
+            brace_style: BraceStyle::SameLine,
+            newline_style: NewlineStyle::Unix,
+            max_width: 100,
+        }
+    }
+}
+
+impl Default for FormatterConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adapts any [`io::Write`] sink to [`fmt::Write`] so a [`Formatter`] can stream its
+/// output directly to e.g. a `BufWriter<File>` instead of buffering a whole `String`.
+///
+/// An I/O error encountered while writing is stashed and surfaced as [`fmt::Error`]; the
+/// original [`io::Error`] can be recovered afterwards with [`IoWriter::take_error`].
+#[derive(Debug)]
+pub struct IoWriter<W: io::Write> {
+    /// the wrapped I/O sink
+    inner: W,
+
+    /// the last I/O error, if any occurred
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    /// Returns a new adapter wrapping the given I/O sink.
+    pub fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Takes the last I/O error that occurred, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Unwraps the adapter, returning the underlying I/O sink.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> fmt::Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
 /// Formatter for a scope.
 #[derive(Debug)]
-pub struct Formatter<'a> {
+pub struct Formatter<'a, W: fmt::Write = String> {
     /// THe desination buffer for the formatter
-    dst: &'a mut String,
+    dst: &'a mut W,
 
     /// The current indentation level
     spaces: usize,
 
     /// the current scope
     scope: Vec<String>,
+
+    /// the layout configuration
+    config: FormatterConfig,
+
+    /// the resolved line terminator (with `Native` mapped to the host default)
+    newline: &'static str,
+
+    /// whether the next byte would start a new line
+    start_of_line: bool,
+
+    /// the column (in bytes) at which the next byte would be written
+    column: usize,
 }
 
-impl<'a> Formatter<'a> {
+impl<'a> Formatter<'a, String> {
     /// Returns a new formatter instance.
     pub fn new(dst: &'a mut String) -> Self {
+        Self::with_config(dst, FormatterConfig::new())
+    }
+}
+
+impl<'a, W: fmt::Write> Formatter<'a, W> {
+    /// Returns a new formatter instance using the supplied configuration.
+    pub fn with_config(dst: &'a mut W, config: FormatterConfig) -> Self {
         Self {
             dst,
             spaces: 0,
             scope: vec![],
+            newline: config.newline_style.terminator(),
+            config,
+            start_of_line: true,
+            column: 0,
         }
     }
 
+    /// writes the configured line terminator into the destination buffer
+    fn push_newline(&mut self) -> fmt::Result {
+        self.dst.write_str(self.newline)?;
+        self.column = 0;
+        Ok(())
+    }
+
     pub fn get_indent(&self) -> usize {
         self.spaces
     }
@@ -75,9 +226,9 @@ impl<'a> Formatter<'a> {
 
     pub fn write_scoped_name(&mut self, name: &str) -> fmt::Result {
         write!(self, " ")?;
-        for s in &self.scope {
-            self.dst.push_str(s);
-            self.dst.push_str("::");
+        let scope = self.scope.join("::");
+        if !scope.is_empty() {
+            write!(self, "{scope}::")?;
         }
         write!(self, "{name}")
     }
@@ -87,8 +238,17 @@ impl<'a> Formatter<'a> {
     where
         F: FnOnce(&mut Self) -> fmt::Result,
     {
-        if !self.is_start_of_line() {
-            write!(self, " ")?;
+        match self.config.brace_style {
+            BraceStyle::SameLine => {
+                if !self.is_start_of_line() {
+                    write!(self, " ")?;
+                }
+            }
+            BraceStyle::NextLine => {
+                if !self.is_start_of_line() {
+                    writeln!(self)?;
+                }
+            }
         }
 
         writeln!(self, "{{")?;
@@ -97,58 +257,325 @@ impl<'a> Formatter<'a> {
         Ok(())
     }
 
+    /// Emits a delimited list, wrapping it across multiple lines when it would be too wide.
+    ///
+    /// The list is first tried inline as `open item<sep>item<sep>item close`. If that would
+    /// push the current line past the configured [`max_width`], it instead falls back to one
+    /// item per line, indented one extra level. Every line but the last ends in the separator
+    /// with its trailing whitespace trimmed; the final item has no separator after it.
+    ///
+    /// [`max_width`]: FormatterConfig::max_width
+    pub fn list<S: AsRef<str>>(
+        &mut self,
+        open: &str,
+        items: &[S],
+        sep: &str,
+        close: &str,
+    ) -> fmt::Result {
+        let items_len: usize = items.iter().map(|i| i.as_ref().len()).sum();
+        let inline_len = open.len()
+            + items_len
+            + sep.len() * items.len().saturating_sub(1)
+            + close.len();
+
+        // when the list opens a fresh line the indentation has not been emitted yet
+        // (it is added later by `push_spaces`), so fold it into the width estimate.
+        let column = self.column + if self.start_of_line { self.spaces } else { 0 };
+
+        if column + inline_len <= self.config.max_width {
+            write!(self, "{open}")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(self, "{sep}")?;
+                }
+                write!(self, "{}", item.as_ref())?;
+            }
+            write!(self, "{close}")?;
+        } else {
+            writeln!(self, "{open}")?;
+            let last = items.len().saturating_sub(1);
+            self.indent(|f| {
+                for (i, item) in items.iter().enumerate() {
+                    // no separator after the final item: a trailing `,` would be invalid
+                    // C in the prototype / argument lists this helper targets.
+                    if i == last {
+                        writeln!(f, "{}", item.as_ref())?;
+                    } else {
+                        // trim trailing whitespace off the separator so wrapped lines
+                        // don't carry trailing spaces into the generated C.
+                        writeln!(f, "{}{}", item.as_ref(), sep.trim_end())?;
+                    }
+                }
+                Ok(())
+            })?;
+            write!(self, "{close}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Splices a literal block of hand-written C into the output at the current scope.
+    ///
+    /// The `text` is first normalized with the indoc unindent algorithm — the common
+    /// leading-whitespace prefix shared by all non-blank lines is stripped — and then
+    /// reindented to the formatter's current indentation level. A leading line that is
+    /// entirely whitespace is dropped and a trailing newline is preserved iff the input
+    /// had one. A single-line input without a newline is emitted verbatim.
+    pub fn raw_block(&mut self, text: &str) -> fmt::Result {
+        // a single line without a newline is emitted verbatim at the current indent
+        if !text.contains('\n') {
+            return self.write_str(text);
+        }
+
+        let had_trailing_newline = text.ends_with('\n');
+
+        // split into lines, dropping the trailing empty element produced by a trailing
+        // newline so it can be re-added explicitly once the block is re-assembled.
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        if had_trailing_newline {
+            lines.pop();
+        }
+
+        // ignore the first line if it is entirely whitespace
+        let start = usize::from(lines.first().is_some_and(|l| l.trim().is_empty()));
+
+        // compute the longest common leading-whitespace prefix over all non-blank lines
+        let mut prefix: Option<&str> = None;
+        for line in &lines[start..] {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let ws = &line[..line.len() - line.trim_start().len()];
+            prefix = Some(match prefix {
+                None => ws,
+                Some(p) => common_prefix(p, ws),
+            });
+        }
+        let prefix = prefix.unwrap_or("");
+
+        // strip the common prefix and re-assemble; blank lines are emitted empty
+        let mut out = String::new();
+        for (i, line) in lines[start..].iter().enumerate() {
+            if i > 0 {
+                out.push('\n');
+            }
+            if !line.trim().is_empty() {
+                out.push_str(&line[prefix.len()..]);
+            }
+        }
+        if had_trailing_newline {
+            out.push('\n');
+        }
+
+        self.write_str(&out)
+    }
+
     /// Formats the function with an increased indentation level
     pub fn indent<F, R>(&mut self, f: F) -> R
     where
         F: FnOnce(&mut Self) -> R,
     {
-        self.spaces += DEFAULT_INDENT;
+        self.spaces += self.config.indent_width;
         let ret = f(self);
-        self.spaces -= DEFAULT_INDENT;
+        self.spaces -= self.config.indent_width;
         ret
     }
 
     /// Check if current destination is the start of a new line.
     pub fn is_start_of_line(&self) -> bool {
-        self.dst.is_empty() || self.dst.ends_with('\n')
+        self.start_of_line
     }
 
-    /// writes spaces into the destination buffer
-    fn push_spaces(&mut self) {
-        for _ in 0..self.spaces {
-            self.dst.push(' ');
+    /// writes the current indentation into the destination buffer
+    fn push_spaces(&mut self) -> fmt::Result {
+        if self.config.use_tabs {
+            let levels = self.spaces / self.config.indent_width;
+            for _ in 0..levels {
+                self.dst.write_char('\t')?;
+            }
+            self.column += levels;
+        } else {
+            for _ in 0..self.spaces {
+                self.dst.write_char(' ')?;
+            }
+            self.column += self.spaces;
         }
+        Ok(())
     }
 }
 
-impl fmt::Write for Formatter<'_> {
+/// Returns the longest common leading substring of `a` and `b`, compared byte-for-byte.
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a
+        .as_bytes()
+        .iter()
+        .zip(b.as_bytes())
+        .take_while(|(x, y)| x == y)
+        .count();
+    &a[..len]
+}
+
+impl<W: fmt::Write> fmt::Write for Formatter<'_, W> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
         let mut first = true;
-        let mut should_indent = self.is_start_of_line();
 
         for line in s.lines() {
             if !first {
-                self.dst.push('\n');
+                self.push_newline()?;
+                self.start_of_line = true;
             }
 
             first = false;
 
-            let do_indent = should_indent && !line.is_empty() && line.as_bytes()[0] != b'\n';
-
-            if do_indent {
-                self.push_spaces();
+            if line.is_empty() {
+                continue;
             }
 
-            // If this loops again, then we just wrote a new line
-            should_indent = true;
+            if self.start_of_line {
+                self.push_spaces()?;
+            }
 
-            self.dst.push_str(line);
+            self.dst.write_str(line)?;
+            self.column += line.len();
+            self.start_of_line = false;
         }
 
         if s.as_bytes().last() == Some(&b'\n') {
-            self.dst.push('\n');
+            self.push_newline()?;
+            self.start_of_line = true;
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::Write;
+
+    /// Renders `f` with the given config and returns the produced string.
+    fn render<F>(config: FormatterConfig, f: F) -> String
+    where
+        F: FnOnce(&mut Formatter<'_, String>) -> fmt::Result,
+    {
+        let mut dst = String::new();
+        {
+            let mut fmt = Formatter::with_config(&mut dst, config);
+            f(&mut fmt).unwrap();
+        }
+        dst
+    }
+
+    #[test]
+    fn indent_emits_spaces() {
+        let config = FormatterConfig {
+            indent_width: 2,
+            use_tabs: false,
+            ..FormatterConfig::new()
+        };
+        let out = render(config, |f| f.indent(|f| writeln!(f, "x")));
+        assert_eq!(out, "  x\n");
+    }
+
+    #[test]
+    fn indent_emits_tabs() {
+        let config = FormatterConfig {
+            indent_width: 2,
+            use_tabs: true,
+            ..FormatterConfig::new()
+        };
+        let out = render(config, |f| f.indent(|f| f.indent(|f| writeln!(f, "x"))));
+        assert_eq!(out, "\t\tx\n");
+    }
+
+    #[test]
+    fn block_same_line_brace() {
+        let out = render(FormatterConfig::new(), |f| {
+            write!(f, "void foo()")?;
+            f.block(|f| writeln!(f, "return;"))
+        });
+        assert_eq!(out, "void foo() {\n    return;\n}");
+    }
+
+    #[test]
+    fn block_next_line_brace() {
+        let config = FormatterConfig {
+            brace_style: BraceStyle::NextLine,
+            ..FormatterConfig::new()
+        };
+        let out = render(config, |f| {
+            write!(f, "void foo()")?;
+            f.block(|f| writeln!(f, "return;"))
+        });
+        assert_eq!(out, "void foo()\n{\n    return;\n}");
+    }
+
+    #[test]
+    fn newline_style_unix() {
+        let config = FormatterConfig {
+            newline_style: NewlineStyle::Unix,
+            ..FormatterConfig::new()
+        };
+        let out = render(config, |f| write!(f, "a\nb\n"));
+        assert_eq!(out, "a\nb\n");
+    }
+
+    #[test]
+    fn newline_style_windows() {
+        let config = FormatterConfig {
+            newline_style: NewlineStyle::Windows,
+            ..FormatterConfig::new()
+        };
+        let out = render(config, |f| write!(f, "a\nb\n"));
+        assert_eq!(out, "a\r\nb\r\n");
+    }
+
+    #[test]
+    fn list_fits_inline() {
+        let out = render(FormatterConfig::new(), |f| {
+            f.list("(", &["a", "b", "c"], ", ", ")")
+        });
+        assert_eq!(out, "(a, b, c)");
+    }
+
+    #[test]
+    fn list_wraps_when_too_wide() {
+        let config = FormatterConfig {
+            max_width: 8,
+            ..FormatterConfig::new()
+        };
+        let out = render(config, |f| f.list("(", &["alpha", "beta"], ", ", ")"));
+        assert_eq!(out, "(\n    alpha,\n    beta\n)");
+    }
+
+    #[test]
+    fn raw_block_unindents_and_reindents() {
+        // the leading whitespace-only line is dropped and the common prefix stripped,
+        // then the snippet is reindented to the formatter's current scope depth.
+        let out = render(FormatterConfig::new(), |f| {
+            f.indent(|f| f.raw_block("\n    int x;\n    return x;\n"))
+        });
+        assert_eq!(out, "    int x;\n    return x;\n");
+    }
+
+    #[test]
+    fn raw_block_single_line_verbatim() {
+        let out = render(FormatterConfig::new(), |f| {
+            f.indent(|f| f.raw_block("int x;"))
+        });
+        assert_eq!(out, "    int x;");
+    }
+
+    #[test]
+    fn raw_block_preserves_missing_trailing_newline() {
+        let out = render(FormatterConfig::new(), |f| f.raw_block("    a\n    b"));
+        assert_eq!(out, "a\nb");
+    }
+
+    #[test]
+    fn raw_block_emits_blank_lines_empty() {
+        let out = render(FormatterConfig::new(), |f| f.raw_block("  a\n\n  b\n"));
+        assert_eq!(out, "a\n\nb\n");
+    }
+}